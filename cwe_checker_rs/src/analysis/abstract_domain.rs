@@ -0,0 +1,573 @@
+//! Abstract domains used by the dataflow analyses in this crate.
+
+use crate::bil::*;
+use crate::prelude::*;
+
+/// A lattice of abstract values with a join (`merge`) operation.
+///
+/// Implementors must form a join-semilattice: `merge` is commutative,
+/// associative and idempotent, and `x.merge(&x.top())` is always `x.top()`.
+pub trait AbstractDomain: Sized + Eq + Clone {
+    /// The unique maximal (least precise) element of the lattice that `self` belongs to.
+    fn top(&self) -> Self;
+
+    /// Compute the least upper bound of `self` and `other`.
+    fn merge(&self, other: &Self) -> Self;
+
+    /// Compute an upper bound of `self` (the value before an iteration of a fixpoint
+    /// loop) and `other` (the value after), chosen so that repeated widening of a
+    /// strictly increasing chain stabilizes after finitely many steps, even on
+    /// domains (like unbounded intervals) that have no finite-height guarantee.
+    ///
+    /// `widen` must itself be an upper bound of both inputs, the same as `merge`;
+    /// the difference is only that it is allowed to overshoot for termination's sake.
+    /// The default implementation falls back to `merge`, which is already exact
+    /// (and terminating) for every domain of finite height.
+    fn widen(&self, other: &Self) -> Self {
+        self.merge(other)
+    }
+}
+
+/// A domain of values that is closed under the operations of the intermediate
+/// language used in this crate, so that dataflow transfer functions can be
+/// expressed purely in terms of abstract values.
+pub trait ValueDomain: AbstractDomain {
+    /// The bit width of values in this domain.
+    fn bitsize(&self) -> BitSize;
+
+    /// The most imprecise value of the given bit width.
+    fn new_top(bitsize: BitSize) -> Self;
+
+    /// Compute the (abstract) result of a binary operation.
+    fn bin_op(&self, op: BinOpType, rhs: &Self) -> Self;
+
+    /// Compute the (abstract) result of a unary operation.
+    fn un_op(&self, op: UnOpType) -> Self;
+
+    /// Extract the bits in `[low_bit, high_bit]` as a new, narrower value.
+    fn extract(&self, low_bit: BitSize, high_bit: BitSize) -> Self;
+
+    /// Sign- or zero-extend (or truncate) the value to `width` bits.
+    fn cast(&self, kind: CastType, width: BitSize) -> Self;
+
+    /// Concatenate two bitvectors, with `self` providing the upper bits.
+    fn concat(&self, other: &Self) -> Self;
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn signed_min(bitsize: BitSize) -> i128 {
+    -(1i128 << (bitsize - 1))
+}
+
+fn signed_max(bitsize: BitSize) -> i128 {
+    (1i128 << (bitsize - 1)) - 1
+}
+
+/// An abstract value of a fixed bit width, represented either as `Top`
+/// (no information), an exact constant, a strided interval `s[l, u]`
+/// denoting the set `{l, l+s, l+2s, ..., u}` (taken modulo `2^bitsize`),
+/// or a bare alignment congruence (`Aligned`, see below).
+///
+/// A constant `c` is the interval `0[c,c]`; `Top` is `1[MIN, MAX]`. Keeping
+/// `Value` as a distinct variant is just a (semantically redundant) fast
+/// path for the extremely common case of an exact constant.
+#[cfg_attr(
+    not(feature = "compact_abstract_domain_serde"),
+    derive(Serialize, Deserialize)
+)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BitvectorDomain {
+    Top(BitSize),
+    Value(Bitvector),
+    Interval {
+        stride: u128,
+        start: i128,
+        end: i128,
+        bitsize: BitSize,
+    },
+    /// A value about which nothing is known except its alignment:
+    /// `v ≡ residue (mod modulus)` with `modulus` a power of two.
+    ///
+    /// Strictly less precise than any `Interval`/`Value` with the same
+    /// congruence (since those also bound the value), but strictly more
+    /// precise than `Top`. Used to track pointer/offset alignment through
+    /// operations (e.g. `AND`-masking) whose exact bounds we cannot compute.
+    Aligned {
+        modulus: u128,
+        residue: i128,
+        bitsize: BitSize,
+    },
+}
+
+impl BitvectorDomain {
+    /// View `self` as a `(stride, start, end)` strided interval.
+    fn as_interval(&self) -> (u128, i128, i128) {
+        use BitvectorDomain::*;
+        match self {
+            Top(bitsize) => (1, signed_min(*bitsize), signed_max(*bitsize)),
+            Value(bitv) => {
+                let val = bitv.try_to_i128();
+                (0, val, val)
+            }
+            Interval { stride, start, end, .. } => (*stride, *start, *end),
+            Aligned { modulus, residue, bitsize } => {
+                let min = signed_min(*bitsize);
+                let max = signed_max(*bitsize);
+                let start = min + (residue - min).rem_euclid(*modulus as i128);
+                let end = max - (max - residue).rem_euclid(*modulus as i128);
+                (*modulus, start, end)
+            }
+        }
+    }
+
+    /// Build the canonical representation for a given strided interval,
+    /// collapsing to `Value` or `Top` whenever that is exact.
+    fn from_interval(stride: u128, start: i128, end: i128, bitsize: BitSize) -> BitvectorDomain {
+        if start == end {
+            return BitvectorDomain::Value(Bitvector::new(start, bitsize));
+        }
+        // Wraparound: the arithmetic that produced these bounds overflowed the
+        // range representable in `bitsize` bits, so `start`/`end` no longer
+        // denote a valid strided interval of `bitsize`-bit values. Collapse to
+        // `Top` rather than keep out-of-range bounds around.
+        if start < signed_min(bitsize) || end > signed_max(bitsize) {
+            return BitvectorDomain::Top(bitsize);
+        }
+        if start == signed_min(bitsize) && end == signed_max(bitsize) {
+            return BitvectorDomain::Top(bitsize);
+        }
+        BitvectorDomain::Interval {
+            stride,
+            start,
+            end,
+            bitsize,
+        }
+    }
+
+    /// The strongest known power-of-two alignment of `self`, as `(modulus, residue)`
+    /// with `v ≡ residue (mod modulus)` for every value `v` the domain may represent.
+    pub fn alignment(&self) -> (u128, i128) {
+        use BitvectorDomain::*;
+        match self {
+            Top(_) => (1, 0),
+            Value(bitv) => {
+                let value = bitv.try_to_i128();
+                let modulus = if value == 0 {
+                    1u128 << (bitv.bitsize() - 1)
+                } else {
+                    1u128 << value.unsigned_abs().trailing_zeros()
+                };
+                (modulus, 0)
+            }
+            Interval { stride, start, .. } => {
+                let modulus = 1u128 << stride.trailing_zeros();
+                (modulus, start.rem_euclid(modulus as i128))
+            }
+            Aligned { modulus, residue, .. } => (*modulus, *residue),
+        }
+    }
+
+    /// Round down to the nearest multiple of `2^log2_modulus`, as happens when masking
+    /// a value with an `AND`-mask that clears its low `log2_modulus` bits.
+    pub fn round_down_to_alignment(&self, log2_modulus: BitSize) -> BitvectorDomain {
+        let modulus = 1u128 << log2_modulus;
+        match self {
+            BitvectorDomain::Value(bitv) => {
+                let value = bitv.try_to_i128();
+                let rounded = value.div_euclid(modulus as i128) * modulus as i128;
+                BitvectorDomain::Value(Bitvector::new(rounded, bitv.bitsize()))
+            }
+            _ => BitvectorDomain::Aligned {
+                modulus,
+                residue: 0,
+                bitsize: self.bitsize(),
+            },
+        }
+    }
+}
+
+impl ValueDomain for BitvectorDomain {
+    fn bitsize(&self) -> BitSize {
+        use BitvectorDomain::*;
+        match self {
+            Top(bitsize) => *bitsize,
+            Value(bitv) => bitv.bitsize(),
+            Interval { bitsize, .. } => *bitsize,
+            Aligned { bitsize, .. } => *bitsize,
+        }
+    }
+
+    fn new_top(bitsize: BitSize) -> BitvectorDomain {
+        BitvectorDomain::Top(bitsize)
+    }
+
+    fn bin_op(&self, op: BinOpType, rhs: &Self) -> Self {
+        use BinOpType::*;
+        let bitsize = self.bitsize();
+        let (s1, l1, u1) = self.as_interval();
+        let (s2, l2, u2) = rhs.as_interval();
+        match op {
+            PLUS => {
+                let stride = gcd(s1, s2);
+                Self::from_interval(stride, l1 + l2, u1 + u2, bitsize)
+            }
+            MINUS => {
+                let stride = gcd(s1, s2);
+                Self::from_interval(stride, l1 - u2, u1 - l2, bitsize)
+            }
+            TIMES => match (s1, l1, u1, s2, l2, u2) {
+                // Multiplying by a constant just scales the stride and bounds; a
+                // negative constant also flips which scaled bound is the lower one.
+                (0, c, c2, _, _, _) if c == c2 => {
+                    let (lo, hi) = (l2 * c, u2 * c);
+                    Self::from_interval(s2 * c.unsigned_abs(), lo.min(hi), lo.max(hi), bitsize)
+                }
+                (_, _, _, 0, c, c2) if c == c2 => {
+                    let (lo, hi) = (l1 * c, u1 * c);
+                    Self::from_interval(s1 * c.unsigned_abs(), lo.min(hi), lo.max(hi), bitsize)
+                }
+                _ => Self::new_top(bitsize),
+            },
+            _ => Self::new_top(bitsize),
+        }
+    }
+
+    fn un_op(&self, op: UnOpType) -> Self {
+        use UnOpType::*;
+        let bitsize = self.bitsize();
+        let (stride, start, end) = self.as_interval();
+        match op {
+            NEG => Self::from_interval(stride, -end, -start, bitsize),
+            // NOT is the affine transform `x -> -x - 1` in two's complement.
+            NOT => Self::from_interval(stride, -end - 1, -start - 1, bitsize),
+        }
+    }
+
+    fn extract(&self, low_bit: BitSize, high_bit: BitSize) -> Self {
+        if let BitvectorDomain::Value(bitv) = self {
+            let value = bitv.try_to_i128();
+            let width = high_bit - low_bit + 1;
+            let extracted = (value >> low_bit) & ((1i128 << width) - 1);
+            BitvectorDomain::Value(Bitvector::new(extracted, width))
+        } else {
+            Self::new_top(high_bit - low_bit + 1)
+        }
+    }
+
+    fn cast(&self, kind: CastType, width: BitSize) -> Self {
+        use BitvectorDomain::*;
+        match (self, kind) {
+            (Value(bitv), _) if width == 32 || width == 64 => {
+                let value = bitv.try_to_i128();
+                // Zero extension reads the source bits as an unsigned number.
+                let value = if kind == CastType::UNSIGNED && width > bitv.bitsize() {
+                    value & ((1i128 << bitv.bitsize()) - 1)
+                } else {
+                    value
+                };
+                Value(Bitvector::new(value, width))
+            }
+            // Sign extension preserves the value set of an interval, and so does zero
+            // extension of an interval without negative values; every other cast of an
+            // interval (truncation included) falls back to `Top`.
+            (Interval { stride, start, end, bitsize }, CastType::SIGNED | CastType::UNSIGNED)
+                if width >= *bitsize && (kind == CastType::SIGNED || *start >= 0) =>
+            {
+                Interval {
+                    stride: *stride,
+                    start: *start,
+                    end: *end,
+                    bitsize: width,
+                }
+            }
+            _ => Self::new_top(width),
+        }
+    }
+
+    fn concat(&self, other: &Self) -> Self {
+        if let (BitvectorDomain::Value(upper), BitvectorDomain::Value(lower)) = (self, other) {
+            let lower_bits = lower.try_to_i128() & ((1i128 << other.bitsize()) - 1);
+            let combined = (upper.try_to_i128() << other.bitsize()) | lower_bits;
+            BitvectorDomain::Value(Bitvector::new(combined, self.bitsize() + other.bitsize()))
+        } else {
+            Self::new_top(self.bitsize() + other.bitsize())
+        }
+    }
+}
+
+impl AbstractDomain for BitvectorDomain {
+    fn top(&self) -> Self {
+        BitvectorDomain::Top(self.bitsize())
+    }
+
+    fn merge(&self, other: &Self) -> Self {
+        if self == other {
+            return self.clone();
+        }
+        let bitsize = self.bitsize();
+        let (s1, l1, u1) = self.as_interval();
+        let (s2, l2, u2) = other.as_interval();
+        let stride = gcd(gcd(s1, s2), (l1 - l2).unsigned_abs());
+        Self::from_interval(stride, l1.min(l2), u1.max(u2), bitsize)
+    }
+
+    /// Classic interval widening: a bound that grew since the last iteration is
+    /// pushed straight to the domain extreme instead of tracking its new, still
+    /// possibly-unstable value, and a stride that changed is dropped to `1`.
+    fn widen(&self, other: &Self) -> Self {
+        if self == other {
+            return self.clone();
+        }
+        let bitsize = self.bitsize();
+        let (old_stride, old_start, old_end) = self.as_interval();
+        let (new_stride, new_start, new_end) = other.as_interval();
+        let stride = gcd(gcd(old_stride, new_stride), (old_start - new_start).unsigned_abs());
+        let stride = if stride == old_stride { old_stride } else { 1 };
+        let start = if new_start < old_start {
+            signed_min(bitsize)
+        } else {
+            old_start.min(new_start)
+        };
+        let end = if new_end > old_end {
+            signed_max(bitsize)
+        } else {
+            old_end.max(new_end)
+        };
+        Self::from_interval(stride, start, end, bitsize)
+    }
+}
+
+/// A compact serde encoding for [`BitvectorDomain`], enabled via the
+/// `compact_abstract_domain_serde` feature. Instead of the derived, self-describing
+/// struct/enum encoding, every variant is written as a flat tuple of its fields, which
+/// is considerably smaller for the large serialized analysis states this crate passes
+/// around. The default (feature disabled) encoding is kept as-is so that JSON output
+/// meant for humans to read while debugging stays self-describing.
+#[cfg(feature = "compact_abstract_domain_serde")]
+mod compact_serde {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    enum Wire {
+        Top(BitSize),
+        Value(Bitvector),
+        Interval(u128, i128, i128, BitSize),
+        Aligned(u128, i128, BitSize),
+    }
+
+    impl From<&BitvectorDomain> for Wire {
+        fn from(domain: &BitvectorDomain) -> Self {
+            match domain {
+                BitvectorDomain::Top(bitsize) => Wire::Top(*bitsize),
+                BitvectorDomain::Value(bitv) => Wire::Value(*bitv),
+                BitvectorDomain::Interval { stride, start, end, bitsize } => {
+                    Wire::Interval(*stride, *start, *end, *bitsize)
+                }
+                BitvectorDomain::Aligned { modulus, residue, bitsize } => {
+                    Wire::Aligned(*modulus, *residue, *bitsize)
+                }
+            }
+        }
+    }
+
+    impl From<Wire> for BitvectorDomain {
+        fn from(wire: Wire) -> Self {
+            match wire {
+                Wire::Top(bitsize) => BitvectorDomain::Top(bitsize),
+                Wire::Value(bitv) => BitvectorDomain::Value(bitv),
+                Wire::Interval(stride, start, end, bitsize) => {
+                    BitvectorDomain::Interval { stride, start, end, bitsize }
+                }
+                Wire::Aligned(modulus, residue, bitsize) => {
+                    BitvectorDomain::Aligned { modulus, residue, bitsize }
+                }
+            }
+        }
+    }
+
+    impl Serialize for BitvectorDomain {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            Wire::from(self).serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for BitvectorDomain {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Wire::deserialize(deserializer).map(BitvectorDomain::from)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constants_merge_to_a_strided_interval() {
+        let c42 = BitvectorDomain::Value(Bitvector::from_i64(42));
+        let c41 = BitvectorDomain::Value(Bitvector::from_i64(41));
+        assert_eq!(
+            c42.merge(&c41),
+            BitvectorDomain::Interval {
+                stride: 1,
+                start: 41,
+                end: 42,
+                bitsize: 64,
+            }
+        );
+    }
+
+    #[test]
+    fn strided_merge_keeps_a_common_stride() {
+        let c0 = BitvectorDomain::Value(Bitvector::from_i64(0));
+        let c40 = BitvectorDomain::Value(Bitvector::from_i64(40));
+        let interval = c0.merge(&c40);
+        let c4 = BitvectorDomain::Value(Bitvector::from_i64(4));
+        assert_eq!(
+            interval.merge(&c4),
+            BitvectorDomain::Interval {
+                stride: 4,
+                start: 0,
+                end: 40,
+                bitsize: 64,
+            }
+        );
+    }
+
+    #[test]
+    fn interval_plus_preserves_stride() {
+        let interval = BitvectorDomain::Interval {
+            stride: 4,
+            start: 0,
+            end: 40,
+            bitsize: 64,
+        };
+        let four = BitvectorDomain::Value(Bitvector::from_i64(4));
+        assert_eq!(
+            interval.bin_op(BinOpType::PLUS, &four),
+            BitvectorDomain::Interval {
+                stride: 4,
+                start: 4,
+                end: 44,
+                bitsize: 64,
+            }
+        );
+    }
+
+    #[test]
+    fn times_by_a_negative_constant_swaps_the_bounds() {
+        let interval = BitvectorDomain::Interval {
+            stride: 1,
+            start: 0,
+            end: 10,
+            bitsize: 64,
+        };
+        let neg_two = BitvectorDomain::Value(Bitvector::from_i64(-2));
+        assert_eq!(
+            interval.bin_op(BinOpType::TIMES, &neg_two),
+            BitvectorDomain::Interval {
+                stride: 2,
+                start: -20,
+                end: 0,
+                bitsize: 64,
+            }
+        );
+    }
+
+    #[test]
+    fn widen_pushes_growing_bounds_to_extremes() {
+        let bitsize = 64;
+        let old = BitvectorDomain::Interval {
+            stride: 4,
+            start: 0,
+            end: 40,
+            bitsize,
+        };
+        let new = BitvectorDomain::Interval {
+            stride: 4,
+            start: 0,
+            end: 44,
+            bitsize,
+        };
+        assert_eq!(
+            old.widen(&new),
+            BitvectorDomain::Interval {
+                stride: 4,
+                start: 0,
+                end: signed_max(bitsize),
+                bitsize,
+            }
+        );
+    }
+
+    #[test]
+    fn merging_the_full_range_collapses_to_top() {
+        let bitsize = 64;
+        let full = BitvectorDomain::Interval {
+            stride: 1,
+            start: signed_min(bitsize),
+            end: signed_max(bitsize),
+            bitsize,
+        };
+        let c0 = BitvectorDomain::Value(Bitvector::from_i64(0));
+        assert_eq!(full.merge(&c0), BitvectorDomain::new_top(bitsize));
+    }
+
+    #[test]
+    fn plus_collapses_to_top_on_partial_overflow() {
+        let bitsize = 64;
+        let near_max = BitvectorDomain::Interval {
+            stride: 1,
+            start: signed_max(bitsize) - 5,
+            end: signed_max(bitsize),
+            bitsize,
+        };
+        // Only the upper part of the interval overflows: `max - 2` still fits.
+        let three = BitvectorDomain::Value(Bitvector::from_i64(3));
+        assert_eq!(
+            near_max.bin_op(BinOpType::PLUS, &three),
+            BitvectorDomain::new_top(bitsize)
+        );
+    }
+
+    #[test]
+    fn collapsing_to_a_value_preserves_bitsize() {
+        let extracted = BitvectorDomain::Value(Bitvector::from_i64(5)).extract(0, 31);
+        let doubled = extracted.bin_op(BinOpType::PLUS, &extracted);
+        assert_eq!(doubled.bitsize(), 32);
+    }
+
+    #[cfg(feature = "compact_abstract_domain_serde")]
+    #[test]
+    fn compact_serde_round_trips_every_variant() {
+        let values = vec![
+            BitvectorDomain::Top(64),
+            BitvectorDomain::Top(32),
+            BitvectorDomain::Value(Bitvector::from_i64(42)),
+            BitvectorDomain::Interval {
+                stride: 4,
+                start: 0,
+                end: 40,
+                bitsize: 64,
+            },
+            BitvectorDomain::Aligned {
+                modulus: 16,
+                residue: 5,
+                bitsize: 64,
+            },
+        ];
+        for value in values {
+            let serialized = serde_json::to_string(&value).unwrap();
+            let deserialized: BitvectorDomain = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(value, deserialized);
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod abstract_domain;
+pub mod pointer_inference;
@@ -18,62 +18,252 @@ impl Data {
     }
 }
 
-/// An abstract value representing a pointer given as a map from an abstract identifier
-/// to the offset in the pointed to object.
+/// All `bitsize` low bits set, i.e. the bit pattern of an all-ones value of that width.
+fn bitsize_mask(bitsize: BitSize) -> u128 {
+    if bitsize >= 128 {
+        u128::MAX
+    } else {
+        (1u128 << bitsize) - 1
+    }
+}
+
+/// If `mask` (within `full_mask`) clears exactly its low `k >= 1` bits and sets every bit
+/// above that, as in the rounding-down idiom `ptr & ~(align - 1)`, return `k`.
+/// The full mask itself (`k = 0`) yields `None`.
+fn low_zero_mask_width(mask: u128, full_mask: u128) -> Option<BitSize> {
+    if mask == full_mask || mask.trailing_zeros() >= 128 {
+        return None;
+    }
+    let k = mask.trailing_zeros() as BitSize;
+    if mask == full_mask & !((1u128 << k) - 1) {
+        Some(k)
+    } else {
+        None
+    }
+}
+
+/// If `mask` consists of exactly its low `k` bits set and nothing else, as in the
+/// small-alignment-mask idiom `ptr & (align - 1)`, return `k`.
+fn low_ones_mask_width(mask: u128) -> Option<BitSize> {
+    if mask & mask.wrapping_add(1) != 0 {
+        return None;
+    }
+    Some(mask.count_ones() as BitSize)
+}
+
+/// The default cap on the number of distinct targets a [`PointerDomain`] tracks explicitly
+/// before collapsing to [`PointerDomain::AnyTarget`]. Analyses that need a different
+/// trade-off between precision and scalability on large binaries can call the
+/// `_with_cap` variants of `merge`/`widen` with their own configured value instead.
+pub const DEFAULT_MAX_POINTER_TARGETS: usize = 100;
+
+/// An abstract value representing a pointer: either a map from abstract identifiers to
+/// the offset in the pointed-to object the pointer may point to, or (once merging has
+/// accumulated more distinct targets than the configured cap) a compact catch-all state
+/// meaning the pointer may point anywhere.
 ///
-/// The map should never be empty. If the map contains more than one key,
-/// it indicates that the pointer may point to any of the contained objects.
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
-pub struct PointerDomain(BTreeMap<AbstractIdentifier, BitvectorDomain>);
+/// The target map should never be empty; a map with more than one key indicates that
+/// the pointer may point to any of the contained objects.
+#[cfg_attr(
+    not(feature = "compact_abstract_domain_serde"),
+    derive(Serialize, Deserialize)
+)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PointerDomain {
+    Targets(BTreeMap<AbstractIdentifier, BitvectorDomain>),
+    AnyTarget(BitSize),
+}
 
 impl PointerDomain {
     pub fn new(target: AbstractIdentifier, offset: BitvectorDomain) -> PointerDomain {
         let mut map = BTreeMap::new();
         map.insert(target, offset);
-        PointerDomain(map)
+        PointerDomain::Targets(map)
     }
 
     /// get the bitsize of the pointer
     pub fn bitsize(&self) -> BitSize {
-        let some_elem = self.0.values().next().unwrap();
-        some_elem.bitsize()
+        match self {
+            PointerDomain::Targets(map) => map.values().next().unwrap().bitsize(),
+            PointerDomain::AnyTarget(bitsize) => *bitsize,
+        }
     }
 
     pub fn merge(&self, other: &PointerDomain) -> PointerDomain {
-        let mut merged_map = self.0.clone();
-        for (location, offset) in other.0.iter() {
-            if merged_map.contains_key(location) {
-                merged_map.insert(location.clone(), merged_map[location].merge(offset));
-            } else {
-                merged_map.insert(location.clone(), offset.clone());
+        self.merge_with_cap(other, DEFAULT_MAX_POINTER_TARGETS)
+    }
+
+    /// Like [`merge`](Self::merge), but collapses to [`PointerDomain::AnyTarget`] as soon
+    /// as the merged target count would exceed `max_targets` instead of the crate-wide default.
+    pub fn merge_with_cap(&self, other: &PointerDomain, max_targets: usize) -> PointerDomain {
+        use PointerDomain::*;
+        match (self, other) {
+            (AnyTarget(bitsize), _) | (_, AnyTarget(bitsize)) => AnyTarget(*bitsize),
+            (Targets(map1), Targets(map2)) => {
+                let mut merged_map = map1.clone();
+                for (location, offset) in map2.iter() {
+                    merged_map
+                        .entry(location.clone())
+                        .and_modify(|old_offset| *old_offset = old_offset.merge(offset))
+                        .or_insert_with(|| offset.clone());
+                }
+                Self::from_map_with_cap(merged_map, self.bitsize(), max_targets)
             }
         }
-        PointerDomain(merged_map)
     }
 
     /// add a value to the offset
     pub fn add_to_offset(&self, value: &BitvectorDomain) -> PointerDomain {
-        let mut result = self.clone();
-        for offset in result.0.values_mut() {
-            *offset = offset.bin_op(BinOpType::PLUS, value);
+        match self {
+            PointerDomain::Targets(map) => {
+                let mut result = map.clone();
+                for offset in result.values_mut() {
+                    *offset = offset.bin_op(BinOpType::PLUS, value);
+                }
+                PointerDomain::Targets(result)
+            }
+            PointerDomain::AnyTarget(bitsize) => PointerDomain::AnyTarget(*bitsize),
         }
-        result
     }
 
     /// subtract a value from the offset
     pub fn sub_from_offset(&self, value: &BitvectorDomain) -> PointerDomain {
-        let mut result = self.clone();
-        for offset in result.0.values_mut() {
-            *offset = offset.bin_op(BinOpType::MINUS, value);
+        match self {
+            PointerDomain::Targets(map) => {
+                let mut result = map.clone();
+                for offset in result.values_mut() {
+                    *offset = offset.bin_op(BinOpType::MINUS, value);
+                }
+                PointerDomain::Targets(result)
+            }
+            PointerDomain::AnyTarget(bitsize) => PointerDomain::AnyTarget(*bitsize),
         }
-        result
     }
 
-    /// Get an iterator over all possible abstract targets (together with the offset in the target) the pointer may point to.
+    /// Get an iterator over all possible abstract targets (together with the offset in
+    /// the target) the pointer may point to, or the bitsize if the pointer has widened
+    /// to the any-target state and no explicit targets are tracked anymore.
     pub fn iter_targets(
         &self,
-    ) -> std::collections::btree_map::Iter<AbstractIdentifier, BitvectorDomain> {
-        self.0.iter()
+    ) -> Result<std::collections::btree_map::Iter<AbstractIdentifier, BitvectorDomain>, BitSize>
+    {
+        match self {
+            PointerDomain::Targets(map) => Ok(map.iter()),
+            PointerDomain::AnyTarget(bitsize) => Err(*bitsize),
+        }
+    }
+
+    /// Widen the offsets of two pointers with the same possible targets, analogous to
+    /// [`merge`](Self::merge) but guaranteeing termination for unbounded offset domains.
+    pub fn widen(&self, other: &PointerDomain) -> PointerDomain {
+        self.widen_with_cap(other, DEFAULT_MAX_POINTER_TARGETS)
+    }
+
+    /// Like [`widen`](Self::widen), but with an explicit target cap (see [`merge_with_cap`](Self::merge_with_cap)).
+    pub fn widen_with_cap(&self, other: &PointerDomain, max_targets: usize) -> PointerDomain {
+        use PointerDomain::*;
+        match (self, other) {
+            (AnyTarget(bitsize), _) | (_, AnyTarget(bitsize)) => AnyTarget(*bitsize),
+            (Targets(map1), Targets(map2)) => {
+                let mut widened_map = map1.clone();
+                for (location, offset) in map2.iter() {
+                    widened_map
+                        .entry(location.clone())
+                        .and_modify(|old_offset| *old_offset = old_offset.widen(offset))
+                        .or_insert_with(|| offset.clone());
+                }
+                Self::from_map_with_cap(widened_map, self.bitsize(), max_targets)
+            }
+        }
+    }
+
+    /// The strongest alignment that is guaranteed to hold for the offset no matter which
+    /// of the possibly several targets the pointer actually points to, as `(modulus, residue)`.
+    pub fn known_alignment(&self) -> (u128, i128) {
+        match self {
+            PointerDomain::Targets(map) => {
+                let mut offsets = map.values();
+                let first = offsets.next().unwrap().clone();
+                offsets.fold(first, |acc, offset| acc.merge(offset)).alignment()
+            }
+            PointerDomain::AnyTarget(_) => (1, 0),
+        }
+    }
+
+    /// Round every target's offset down to the nearest multiple of `2^log2_modulus`,
+    /// as happens when masking a pointer with an `AND`-mask that clears its low bits.
+    pub fn round_offset_down_to_alignment(&self, log2_modulus: BitSize) -> PointerDomain {
+        match self {
+            PointerDomain::Targets(map) => {
+                let mut result = map.clone();
+                for offset in result.values_mut() {
+                    *offset = offset.round_down_to_alignment(log2_modulus);
+                }
+                PointerDomain::Targets(result)
+            }
+            PointerDomain::AnyTarget(bitsize) => PointerDomain::AnyTarget(*bitsize),
+        }
+    }
+
+    /// Collapse to [`AnyTarget`](Self::AnyTarget) if `map` has grown past `max_targets`.
+    fn from_map_with_cap(
+        map: BTreeMap<AbstractIdentifier, BitvectorDomain>,
+        bitsize: BitSize,
+        max_targets: usize,
+    ) -> PointerDomain {
+        if map.len() > max_targets {
+            PointerDomain::AnyTarget(bitsize)
+        } else {
+            PointerDomain::Targets(map)
+        }
+    }
+}
+
+/// A compact serde encoding for [`PointerDomain`], enabled via the same
+/// `compact_abstract_domain_serde` feature as [`BitvectorDomain`]'s. The target map is
+/// written as a length-prefixed list of `(identifier, offset)` pairs instead of a
+/// self-describing map, and the any-target state as just its variant tag and bitsize.
+#[cfg(feature = "compact_abstract_domain_serde")]
+mod compact_serde {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    enum Wire {
+        Targets(Vec<(AbstractIdentifier, BitvectorDomain)>),
+        AnyTarget(BitSize),
+    }
+
+    impl From<&PointerDomain> for Wire {
+        fn from(domain: &PointerDomain) -> Self {
+            match domain {
+                PointerDomain::Targets(map) => {
+                    Wire::Targets(map.iter().map(|(id, offset)| (id.clone(), offset.clone())).collect())
+                }
+                PointerDomain::AnyTarget(bitsize) => Wire::AnyTarget(*bitsize),
+            }
+        }
+    }
+
+    impl From<Wire> for PointerDomain {
+        fn from(wire: Wire) -> Self {
+            match wire {
+                Wire::Targets(entries) => PointerDomain::Targets(entries.into_iter().collect()),
+                Wire::AnyTarget(bitsize) => PointerDomain::AnyTarget(bitsize),
+            }
+        }
+    }
+
+    impl Serialize for PointerDomain {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            Wire::from(self).serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for PointerDomain {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Wire::deserialize(deserializer).map(PointerDomain::from)
+        }
     }
 }
 
@@ -101,7 +291,56 @@ impl ValueDomain for Data {
                 Pointer(pointer.add_to_offset(value))
             }
             (Pointer(pointer), MINUS, Value(value)) => Pointer(pointer.sub_from_offset(value)),
-            // TODO: AND and OR binops may be used to compute pointers when alignment information about the pointer is known.
+            // AND and OR binops may be used to compute pointers when alignment information
+            // about the pointer is known, e.g. the extremely common `ptr & ~0xF` idiom.
+            (Pointer(pointer), AND, Value(BitvectorDomain::Value(mask)))
+            | (Value(BitvectorDomain::Value(mask)), AND, Pointer(pointer)) => {
+                let bitsize = pointer.bitsize();
+                let full_mask = bitsize_mask(bitsize);
+                let mask_bits = (mask.try_to_i128() as u128) & full_mask;
+                let (known_modulus, known_residue) = pointer.known_alignment();
+                if mask_bits == full_mask {
+                    // The identity mask (`ptr & -1`) is a no-op, independent of alignment.
+                    Pointer(pointer.clone())
+                } else if let Some(log2) = low_zero_mask_width(mask_bits, full_mask) {
+                    if (1u128 << log2) <= known_modulus {
+                        Pointer(pointer.round_offset_down_to_alignment(log2))
+                    } else {
+                        ValueDomain::new_top(bitsize)
+                    }
+                } else if let Some(log2) = low_ones_mask_width(mask_bits) {
+                    if (1u128 << log2) <= known_modulus {
+                        // The mask is narrower than the known alignment, so only the
+                        // low `log2` bits of the residue survive the AND.
+                        let reduced = known_residue.rem_euclid(1i128 << log2);
+                        Value(BitvectorDomain::Value(Bitvector::new(reduced, bitsize)))
+                    } else {
+                        ValueDomain::new_top(bitsize)
+                    }
+                } else {
+                    ValueDomain::new_top(bitsize)
+                }
+            }
+            (Pointer(pointer), OR, Value(BitvectorDomain::Value(addend)))
+            | (Value(BitvectorDomain::Value(addend)), OR, Pointer(pointer)) => {
+                let addend_value = addend.try_to_i128();
+                if addend_value == 0 {
+                    // OR-ing in zero is a no-op, independent of alignment.
+                    Pointer(pointer.clone())
+                } else {
+                    let (known_modulus, known_residue) = pointer.known_alignment();
+                    // `ptr | addend` only equals `ptr + addend` if the pointer's low bits
+                    // below the mask are provably zero, and `addend` is small *and*
+                    // non-negative: a negative addend's two's-complement bit pattern sets
+                    // nearly every high bit, which OR is nothing like addition for.
+                    if known_residue == 0 && addend_value >= 0 && addend_value < known_modulus as i128
+                    {
+                        Pointer(pointer.add_to_offset(&BitvectorDomain::Value(*addend)))
+                    } else {
+                        ValueDomain::new_top(pointer.bitsize())
+                    }
+                }
+            }
             _ => ValueDomain::new_top(self.bitsize()),
         }
     }
@@ -157,6 +396,22 @@ impl AbstractDomain for Data {
             (Pointer(_), Value(_)) | (Value(_), Pointer(_)) => Top(self.bitsize()),
         }
     }
+
+    /// Widen `self` (the value before a fixpoint iteration) with `other` (the value
+    /// after), so that iterating a dataflow loop over this (now infinite-height)
+    /// domain still terminates. Pointer targets are widened per-target by
+    /// [`PointerDomain::widen`], which also collapses the pointer to the
+    /// catch-all [`PointerDomain::AnyTarget`] state once it has more than
+    /// [`DEFAULT_MAX_POINTER_TARGETS`] targets.
+    fn widen(&self, other: &Self) -> Self {
+        use Data::*;
+        match (self, other) {
+            (Top(bitsize), _) | (_, Top(bitsize)) => Top(*bitsize),
+            (Pointer(pointer1), Pointer(pointer2)) => Pointer(pointer1.widen(pointer2)),
+            (Value(val1), Value(val2)) => Value(val1.widen(val2)),
+            (Pointer(_), Value(_)) | (Value(_), Pointer(_)) => Top(self.bitsize()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -190,9 +445,16 @@ mod tests {
         let data = new_value(42);
         assert_eq!(pointer.merge(&pointer), pointer);
         assert_eq!(pointer.merge(&data), Data::new_top(64));
+        // Two distinct constants no longer collapse to `Top`: they merge into the
+        // strided interval that tightly covers both of them.
         assert_eq!(
             data.merge(&new_value(41)),
-            Data::Value(BitvectorDomain::new_top(64))
+            Data::Value(BitvectorDomain::Interval {
+                stride: 1,
+                start: 41,
+                end: 42,
+                bitsize: 64,
+            })
         );
 
         let other_pointer = new_pointer("Rbx".into(), 0);
@@ -236,8 +498,201 @@ mod tests {
 
         let other_pointer = new_pointer_domain("Rbx".into(), 5);
         let merged = pointer.merge(&other_pointer);
-        assert_eq!(merged.0.len(), 2);
-        assert_eq!(merged.0.get(&new_id("Rax".into())), Some(&bv(0)));
-        assert_eq!(merged.0.get(&new_id("Rbx".into())), Some(&bv(5)));
+        match merged {
+            PointerDomain::Targets(map) => {
+                assert_eq!(map.len(), 2);
+                assert_eq!(map.get(&new_id("Rax".into())), Some(&bv(0)));
+                assert_eq!(map.get(&new_id("Rbx".into())), Some(&bv(5)));
+            }
+            PointerDomain::AnyTarget(_) => panic!("expected explicit targets"),
+        }
+    }
+
+    #[test]
+    fn pointer_domain_collapses_past_the_target_cap() {
+        let mut pointer = new_pointer_domain("target0".into(), 0);
+        for i in 1..10 {
+            let next = new_pointer_domain(format!("target{}", i), 0);
+            pointer = pointer.merge_with_cap(&next, 10);
+        }
+        // Exactly at the cap: still tracked explicitly.
+        match &pointer {
+            PointerDomain::Targets(map) => assert_eq!(map.len(), 10),
+            PointerDomain::AnyTarget(_) => panic!("collapsed too early"),
+        }
+
+        let one_more = new_pointer_domain("target10".into(), 0);
+        let collapsed = pointer.merge_with_cap(&one_more, 10);
+        assert_eq!(collapsed, PointerDomain::AnyTarget(64));
+
+        // Once collapsed, further merges stay compact instead of growing again.
+        let another = new_pointer_domain("target11".into(), 0);
+        assert_eq!(
+            collapsed.merge_with_cap(&another, 10),
+            PointerDomain::AnyTarget(64)
+        );
+    }
+
+    #[test]
+    fn data_widen_stabilizes_growing_offsets() {
+        let old = Data::Value(BitvectorDomain::Interval {
+            stride: 4,
+            start: 0,
+            end: 40,
+            bitsize: 64,
+        });
+        let new = Data::Value(BitvectorDomain::Interval {
+            stride: 4,
+            start: 0,
+            end: 44,
+            bitsize: 64,
+        });
+        assert_eq!(
+            old.widen(&new),
+            Data::Value(BitvectorDomain::Interval {
+                stride: 4,
+                start: 0,
+                end: i64::MAX as i128,
+                bitsize: 64,
+            })
+        );
+    }
+
+    #[test]
+    fn pointer_and_rounds_down_to_alignment() {
+        use crate::bil::BinOpType::AND;
+        // A pointer known to be 16-byte aligned (e.g. via its allocator), masked
+        // with the common rounding-down idiom `ptr & ~0xF`.
+        let aligned_pointer = Data::Pointer(PointerDomain::new(
+            new_id("Rax".into()),
+            BitvectorDomain::Aligned {
+                modulus: 16,
+                residue: 0,
+                bitsize: 64,
+            },
+        ));
+        let mask = new_value(!0xFi64);
+        match aligned_pointer.bin_op(AND, &mask) {
+            Data::Pointer(_) => (),
+            other => panic!("expected a pointer, got {:?}", other),
+        }
+
+        // Without known alignment (here: an odd offset) the same mask has to degrade to `Top`.
+        let unaligned_pointer = new_pointer("Rax".into(), 7);
+        assert_eq!(unaligned_pointer.bin_op(AND, &mask), Data::new_top(64));
+    }
+
+    #[test]
+    fn pointer_and_extracts_residue() {
+        use crate::bil::BinOpType::AND;
+        let aligned_pointer = Data::Pointer(PointerDomain::new(
+            new_id("Rax".into()),
+            BitvectorDomain::Aligned {
+                modulus: 16,
+                residue: 5,
+                bitsize: 64,
+            },
+        ));
+        let small_mask = new_value(0xF);
+        assert_eq!(aligned_pointer.bin_op(AND, &small_mask), new_value(5));
+    }
+
+    #[test]
+    fn pointer_or_below_alignment_adjusts_offset() {
+        use crate::bil::BinOpType::OR;
+        let aligned_pointer = Data::Pointer(PointerDomain::new(
+            new_id("Rax".into()),
+            BitvectorDomain::Aligned {
+                modulus: 16,
+                residue: 0,
+                bitsize: 64,
+            },
+        ));
+        match aligned_pointer.bin_op(OR, &new_value(3)) {
+            Data::Pointer(_) => (),
+            other => panic!("expected a pointer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pointer_or_with_nonzero_residue_is_not_plain_addition() {
+        use crate::bil::BinOpType::OR;
+        // `≡5 (mod 16)` means the low bits are already partially set (`...0101`), so
+        // `ptr | 3` (`...0101 | ...0011 = ...0111`) is not the same as `ptr + 3`.
+        let aligned_pointer = Data::Pointer(PointerDomain::new(
+            new_id("Rax".into()),
+            BitvectorDomain::Aligned {
+                modulus: 16,
+                residue: 5,
+                bitsize: 64,
+            },
+        ));
+        assert_eq!(aligned_pointer.bin_op(OR, &new_value(3)), Data::new_top(64));
+    }
+
+    #[test]
+    fn pointer_and_identity_mask_is_a_no_op() {
+        use crate::bil::BinOpType::AND;
+        let pointer = new_pointer("Rax".into(), 7);
+        assert_eq!(pointer.bin_op(AND, &new_value(-1)), pointer);
+    }
+
+    #[test]
+    fn pointer_and_mask_narrower_than_alignment_reduces_the_residue() {
+        use crate::bil::BinOpType::AND;
+        // `≡5 (mod 16)` means the low 4 bits are `...0101`, but a 2-bit mask only
+        // keeps `...01 = 1`; returning `5` verbatim would claim an impossible value.
+        let aligned_pointer = Data::Pointer(PointerDomain::new(
+            new_id("Rax".into()),
+            BitvectorDomain::Aligned {
+                modulus: 16,
+                residue: 5,
+                bitsize: 64,
+            },
+        ));
+        let narrow_mask = new_value(0x3);
+        assert_eq!(aligned_pointer.bin_op(AND, &narrow_mask), new_value(1));
+    }
+
+    #[test]
+    fn pointer_or_zero_is_a_no_op() {
+        use crate::bil::BinOpType::OR;
+        let pointer = new_pointer("Rax".into(), 7);
+        assert_eq!(pointer.bin_op(OR, &new_value(0)), pointer);
+    }
+
+    #[test]
+    fn pointer_or_negative_addend_is_not_plain_addition() {
+        use crate::bil::BinOpType::OR;
+        // A negative addend's two's-complement bit pattern sets nearly every high
+        // bit, which OR is nothing like addition for, even with zero residue.
+        let aligned_pointer = Data::Pointer(PointerDomain::new(
+            new_id("Rax".into()),
+            BitvectorDomain::Aligned {
+                modulus: 16,
+                residue: 0,
+                bitsize: 64,
+            },
+        ));
+        assert_eq!(
+            aligned_pointer.bin_op(OR, &new_value(-3)),
+            Data::new_top(64)
+        );
+    }
+
+    #[cfg(feature = "compact_abstract_domain_serde")]
+    #[test]
+    fn compact_serde_round_trips_pointer_domain() {
+        let values = vec![
+            new_pointer_domain("Rax".into(), 0),
+            new_pointer_domain("Rax".into(), 3)
+                .merge(&new_pointer_domain("Rbx".into(), 5)),
+            PointerDomain::AnyTarget(64),
+        ];
+        for value in values {
+            let serialized = serde_json::to_string(&value).unwrap();
+            let deserialized: PointerDomain = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(value, deserialized);
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,23 @@
+use crate::bil::Tid;
+use crate::prelude::*;
+
+/// The location of a variable or memory object that an abstract identifier may refer to.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub enum AbstractLocation {
+    Register(String, BitSize),
+}
+
+/// An abstract identifier uniquely names an abstract object (e.g. a stack frame
+/// or heap allocation) by the term at which it came into existence together
+/// with the location under which it is accessed.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub struct AbstractIdentifier {
+    time: Tid,
+    location: AbstractLocation,
+}
+
+impl AbstractIdentifier {
+    pub fn new(time: Tid, location: AbstractLocation) -> AbstractIdentifier {
+        AbstractIdentifier { time, location }
+    }
+}
@@ -0,0 +1,5 @@
+mod data;
+mod identifier;
+
+pub use data::{Data, PointerDomain};
+pub use identifier::{AbstractIdentifier, AbstractLocation};
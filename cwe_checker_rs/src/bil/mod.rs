@@ -0,0 +1,108 @@
+//! Types mirroring BAP's Binary Instruction Language (BIL), the intermediate
+//! representation that the analyses in this crate operate on.
+
+use crate::prelude::*;
+
+/// A binary operation as it occurs in BIL expressions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BinOpType {
+    PLUS,
+    MINUS,
+    TIMES,
+    DIVIDE,
+    SDIVIDE,
+    MOD,
+    SMOD,
+    LSHIFT,
+    RSHIFT,
+    ARSHIFT,
+    AND,
+    OR,
+    XOR,
+    EQ,
+    NEQ,
+    LT,
+    LE,
+    SLT,
+    SLE,
+}
+
+/// A unary operation as it occurs in BIL expressions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UnOpType {
+    NEG,
+    NOT,
+}
+
+/// The kind of a sign- or zero-extending (or truncating) cast.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CastType {
+    UNSIGNED,
+    SIGNED,
+    HIGH,
+    LOW,
+}
+
+/// A term identifier, uniquely naming a point (instruction, block, function, ...) in a binary.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub struct Tid(String);
+
+impl Tid {
+    pub fn new(name: impl Into<String>) -> Tid {
+        Tid(name.into())
+    }
+}
+
+/// A concrete, fixed-width immediate value.
+///
+/// Internally values are kept sign-extended in a wide container so that
+/// arithmetic does not need to special-case the bit width; callers are
+/// responsible for interpreting the result modulo `2^bitsize`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct Bitvector {
+    value: i128,
+    width: BitSize,
+}
+
+impl Bitvector {
+    pub fn from_i64(value: i64) -> Bitvector {
+        Bitvector {
+            value: value as i128,
+            width: 64,
+        }
+    }
+
+    pub fn from_i32(value: i32) -> Bitvector {
+        Bitvector {
+            value: value as i128,
+            width: 32,
+        }
+    }
+
+    /// Build a value of an arbitrary bit width, wrapping `value` into `width`
+    /// bits (two's complement) first. Use [`Bitvector::from_i64`]/[`Bitvector::from_i32`]
+    /// for the common 64-/32-bit cases; this is for callers that only know the
+    /// width at runtime.
+    pub fn new(value: i128, width: BitSize) -> Bitvector {
+        if width >= 128 {
+            return Bitvector { value, width };
+        }
+        let mask = (1i128 << width) - 1;
+        let truncated = value & mask;
+        let sign_bit = 1i128 << (width - 1);
+        let wrapped = if truncated & sign_bit != 0 {
+            truncated | !mask
+        } else {
+            truncated
+        };
+        Bitvector { value: wrapped, width }
+    }
+
+    pub fn bitsize(&self) -> BitSize {
+        self.width
+    }
+
+    pub fn try_to_i128(&self) -> i128 {
+        self.value
+    }
+}
@@ -0,0 +1,6 @@
+//! Crate-wide imports pulled in by (almost) every module.
+
+pub use serde::{Deserialize, Serialize};
+
+/// The width of a bitvector, register or memory access, measured in bits.
+pub type BitSize = u16;